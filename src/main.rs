@@ -1,3 +1,4 @@
+mod parser;
 mod wildcard;
 use wildcard::Wildcard;
 
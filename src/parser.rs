@@ -0,0 +1,194 @@
+//! A small parser-combinator toolkit.
+//!
+//! Primitive parsers are plain functions (or closures) from `&str` to a
+//! [`ParseResult`]; the [`Parser`] trait adds `map`, `and_then`, `or`,
+//! `many0` and `pred` combinators on top of them so a grammar can be built
+//! by composing small, independently testable pieces rather than
+//! hand-rolling a single state machine.
+
+use std::vec::Vec;
+use std::boxed::Box;
+
+/// What a parser returns: the unconsumed remainder of the input together
+/// with the parsed value, or on failure the input at the point where
+/// parsing failed (for a primitive parser, the input it was given).
+pub type ParseResult<'a, Output> = Result<(&'a str, Output), &'a str>;
+
+pub trait Parser<'a, Output> {
+    fn parse(&self, input: &'a str) -> ParseResult<'a, Output>;
+
+    /// Transforms this parser's output with `f`.
+    fn map<F, NewOutput>(self, f: F) -> BoxedParser<'a, NewOutput>
+    where
+        Self: Sized + 'a,
+        Output: 'a,
+        NewOutput: 'a,
+        F: Fn(Output) -> NewOutput + 'a
+    {
+        BoxedParser::new(map(self, f))
+    }
+
+    /// Runs this parser, then feeds its output into `f` to build the next
+    /// parser to run against the remaining input.
+    fn and_then<F, NewOutput, NextParser>(self, f: F) -> BoxedParser<'a, NewOutput>
+    where
+        Self: Sized + 'a,
+        Output: 'a,
+        NewOutput: 'a,
+        NextParser: Parser<'a, NewOutput> + 'a,
+        F: Fn(Output) -> NextParser + 'a
+    {
+        BoxedParser::new(and_then(self, f))
+    }
+
+    /// Falls back to `other` on the same input if this parser fails.
+    fn or<P>(self, other: P) -> BoxedParser<'a, Output>
+    where
+        Self: Sized + 'a,
+        Output: 'a,
+        P: Parser<'a, Output> + 'a
+    {
+        BoxedParser::new(or(self, other))
+    }
+
+    /// Applies this parser zero or more times, collecting the outputs.
+    fn many0(self) -> BoxedParser<'a, Vec<Output>>
+    where
+        Self: Sized + 'a,
+        Output: 'a
+    {
+        BoxedParser::new(many0(self))
+    }
+
+    /// Keeps this parser's result only if it satisfies `predicate`.
+    fn pred<F>(self, predicate: F) -> BoxedParser<'a, Output>
+    where
+        Self: Sized + 'a,
+        Output: 'a,
+        F: Fn(&Output) -> bool + 'a
+    {
+        BoxedParser::new(pred(self, predicate))
+    }
+}
+
+impl<'a, F, Output> Parser<'a, Output> for F
+where
+    F: Fn(&'a str) -> ParseResult<'a, Output>
+{
+    fn parse(&self, input: &'a str) -> ParseResult<'a, Output> {
+        self(input)
+    }
+}
+
+/// A type-erased, boxed `Parser`, used to stop combinator types from
+/// growing unboundedly as parsers are composed.
+pub struct BoxedParser<'a, Output> {
+    parser: Box<dyn Parser<'a, Output> + 'a>
+}
+
+impl<'a, Output> BoxedParser<'a, Output> {
+    pub fn new<P>(parser: P) -> Self where P: Parser<'a, Output> + 'a {
+        BoxedParser { parser: Box::new(parser) }
+    }
+}
+
+impl<'a, Output> Parser<'a, Output> for BoxedParser<'a, Output> {
+    fn parse(&self, input: &'a str) -> ParseResult<'a, Output> {
+        self.parser.parse(input)
+    }
+}
+
+fn map<'a, P, F, A, B>(parser: P, f: F) -> impl Parser<'a, B>
+where
+    P: Parser<'a, A>,
+    F: Fn(A) -> B
+{
+    move |input| parser.parse(input).map(|(rest, a)| (rest, f(a)))
+}
+
+fn and_then<'a, P, F, A, B, NextP>(parser: P, f: F) -> impl Parser<'a, B>
+where
+    P: Parser<'a, A>,
+    NextP: Parser<'a, B>,
+    F: Fn(A) -> NextP
+{
+    move |input| parser.parse(input).and_then(|(rest, a)| f(a).parse(rest))
+}
+
+fn or<'a, P1, P2, A>(first: P1, second: P2) -> impl Parser<'a, A>
+where
+    P1: Parser<'a, A>,
+    P2: Parser<'a, A>
+{
+    move |input| first.parse(input).or_else(|_| second.parse(input))
+}
+
+fn many0<'a, P, A>(parser: P) -> impl Parser<'a, Vec<A>>
+where
+    P: Parser<'a, A>
+{
+    move |mut input| {
+        let mut result = Vec::new();
+        while let Ok((rest, item)) = parser.parse(input) {
+            input = rest;
+            result.push(item);
+        }
+        Ok((input, result))
+    }
+}
+
+fn pred<'a, P, A, F>(parser: P, predicate: F) -> impl Parser<'a, A>
+where
+    P: Parser<'a, A>,
+    F: Fn(&A) -> bool
+{
+    move |input| match parser.parse(input) {
+        Ok((rest, value)) if predicate(&value) => Ok((rest, value)),
+        _ => Err(input)
+    }
+}
+
+/// Consumes and returns the next `char`, failing on empty input.
+pub fn any_char(input: &str) -> ParseResult<'_, char> {
+    match input.chars().next() {
+        Some(c) => Ok((&input[c.len_utf8()..], c)),
+        None => Err(input)
+    }
+}
+
+/// Matches exactly `expected` and returns it.
+pub fn literal_char<'a>(expected: char) -> impl Parser<'a, char> {
+    pred(any_char, move |&c| c == expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_transforms_output() {
+        let parser = literal_char('a').map(|c| c.to_ascii_uppercase());
+        assert_eq!(parser.parse("abc"), Ok(("bc", 'A')));
+    }
+
+    #[test]
+    fn or_falls_back() {
+        let parser = literal_char('a').or(literal_char('b'));
+        assert_eq!(parser.parse("bc"), Ok(("c", 'b')));
+        assert_eq!(parser.parse("cd"), Err("cd"));
+    }
+
+    #[test]
+    fn many0_collects_zero_or_more() {
+        let parser = literal_char('a').many0();
+        assert_eq!(parser.parse("aaab"), Ok(("b", vec!['a', 'a', 'a'])));
+        assert_eq!(parser.parse("b"), Ok(("b", Vec::new())));
+    }
+
+    #[test]
+    fn pred_rejects_non_matching_output() {
+        let parser = any_char.pred(|c| c.is_ascii_digit());
+        assert_eq!(parser.parse("1a"), Ok(("a", '1')));
+        assert_eq!(parser.parse("a1"), Err("a1"));
+    }
+}
@@ -2,8 +2,10 @@
 
 use std::vec::Vec;
 use std::boxed::Box;
-// use std::fmt;
-// use std::fmt::{Display, Formatter, Write};
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use crate::parser::{Parser, ParseResult, any_char, literal_char};
 
 /// Any possible production of a wildcard grammar.
 ///
@@ -11,7 +13,10 @@ use std::boxed::Box;
 /// of expressiveness we can get.
 #[derive(Debug, PartialEq)]
 enum Production<'a> {
-    Sequence(&'a str),
+    /// Borrowed straight from the source when it has no escapes, owned
+    /// when a `\` spliced an escaped character into an otherwise
+    /// contiguous run.
+    Sequence(Cow<'a, str>),
     ManyOf(Vec<Choice<'a>>),
     OneOf(Vec<Choice<'a>>),
     Not(Box<Production<'a>>)
@@ -38,227 +43,254 @@ impl<'a> Wildcard<'a> {
     pub fn new() -> Self { Self(Vec::<_>::new()) }
 }
 
-/// Errors which can occur during parsing string as a wildcard.
-#[derive(Debug, PartialEq)]
-pub enum WildcardParseError {
-    Incomplete,
-    InvalidCharRange(char, char)
-}
-
-/// Type to hold some state during parsing string as a wildcard.
-#[derive(Debug)]
-struct WildcardParser<'a> {
+/// A location within a parsed source string, kept alongside the source
+/// itself so a `WildcardParseError` can render a caret pointing at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position<'a> {
     source: &'a str,
-    result: Wildcard<'a>,
-    /// Where has a capture started?
-    start: Option<usize>,
-    /// Should we negate next token?
-    negate: bool
+    /// Byte offset of the offending character into `source`.
+    index: usize
 }
 
-impl<'a> WildcardParser<'a> {
-
-    fn new(source: &'a str) -> WildcardParser<'a> {
-        Self {
-            source,
-            result: Wildcard::new(),
-            start: None,
-            negate: false
+impl std::fmt::Display for Position<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let column = self.source[.. self.index].chars().count();
+        writeln!(f, "{}", self.source)?;
+        for _ in 0 .. column {
+            f.write_char(' ')?;
         }
+        f.write_char('^')
     }
+}
 
-    /// Proceeds with parsing.
-    ///
-    /// Spits out a `Grammar` if string is valid wildcard representation,
-    /// or some `GrammarParseError` otherwise.
-    fn run(&mut self) -> Result<Wildcard<'a>, WildcardParseError> {
-        use Production::*;
-        use Choice::*;
-        use WildcardParseError::*;
-        let mut token = Sequence(self.source);
-        let mut iter = self.source.char_indices();
-        let mut prev: (usize, char) = Default::default();
-        while let Some((index, c)) = iter.next() {
-            if self.start.is_none() {
-                self.start = Some(index);
-            }
-            match c {
-                '*' =>
-                    if let Sequence(_) = &token {
-                        self.flush(index);
-                        self.reset_capture();
-                        self.push(ManyOf(Vec::new()));
-                    },
-
-                '?' =>
-                    if let Sequence(_) = &token {
-                        self.flush(index);
-                        self.reset_capture();
-                        self.push(OneOf(Vec::new()));
-                    },
-
-                '[' =>
-                    if let Sequence(_) = &token {
-                        self.flush(index);
-                        token = OneOf(Vec::new());
-                        let (index, c) = iter.next().ok_or(Incomplete)?;
-                        self.start_capture(index);
-                        if '!' == c {
-                            self.negate = true;
-                            self.reset_capture();
-                        }
-                    },
+/// Locates `at` (a substring of `source`) within `source`.
+fn position_of<'a>(source: &'a str, at: &'a str) -> Position<'a> {
+    Position { source, index: at.as_ptr() as usize - source.as_ptr() as usize }
+}
 
-                '-' => {
-                    if let OneOf(choices) = &mut token {
-                        if let Some(capture) = self.capture(prev.0) {
-                            choices.push(AnyOf(capture));
-                        }
-                        let (_, c) = iter.next().ok_or(Incomplete)?;
-                        if c < prev.1 {
-                            return Err(InvalidCharRange(prev.1, c))
-                        }
-                        self.reset_capture();
-                        choices.push(Range(prev.1, c));
-                    }
-                }
+/// Errors which can occur during parsing string as a wildcard.
+#[derive(Debug, PartialEq)]
+pub enum WildcardParseError<'a> {
+    /// Parsing ran out of input before a production (most commonly a
+    /// `[...]` class) was closed. Points at where that production opened.
+    Incomplete(Position<'a>),
+    /// A `[...]` class contained a `from-to` range with `from > to`.
+    /// Points at the `-` of the offending range.
+    InvalidCharRange(char, char, Position<'a>)
+}
 
-                ']' =>
-                    if let OneOf(choices) = &mut token {
-                        if let Some(capture) = self.capture(index) {
-                            choices.push(AnyOf(capture));
-                        }
-                        let token = std::mem::replace(&mut token, Sequence(self.source));
-                        self.reset_capture();
-                        self.push(token);
-                    }
+impl std::fmt::Display for WildcardParseError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WildcardParseError::Incomplete(position) =>
+                writeln!(f, "incomplete wildcard pattern:").and_then(|_| position.fmt(f)),
+            WildcardParseError::InvalidCharRange(from, to, position) =>
+                writeln!(f, "invalid char range '{}-{}': '{}' is past '{}':", from, to, from, to)
+                    .and_then(|_| position.fmt(f))
+        }
+    }
+}
 
-                _ =>
-                    ()
+/// Is this character a meta character, i.e. does it start some production
+/// other than a plain `Sequence`?
+fn is_meta(c: char) -> bool {
+    matches!(c, '*' | '?' | '[')
+}
 
-            }
-            prev = (index, c);
-        }
-        if let Sequence(_) = &token {
-            self.flush(self.source.len());
-            let result = std::mem::replace(&mut self.result, Wildcard::new());
-            Ok(result)
-        }
-        else {
-            Err(WildcardParseError::Incomplete)
+/// Unescapes a raw source run: every `\` is dropped and the character it
+/// protected is taken literally.
+fn unescape(raw: &str) -> String {
+    let mut result = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            c => result.push(c)
         }
     }
+    result
+}
 
-    /// Push a `Sequence` to the buffer if there's non-empty active capture.
-    fn flush(&mut self, index: usize) {
-        if let Some(capture) = self.capture(index) {
-            self.push(Production::Sequence(capture));
+/// Consumes a maximal run of non-meta characters as a `Sequence`, treating
+/// `\x` as a literal `x` rather than as the start of some other production.
+/// Fails with the position of the `\` if it's the last character of the
+/// whole input, since there's then nothing left for it to escape.
+fn sequence(input: &str) -> ParseResult<'_, Production<'_>> {
+    let mut has_escape = false;
+    let mut end = 0;
+    let mut chars = input.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, escaped)) => {
+                    has_escape = true;
+                    end = index + 1 + escaped.len_utf8();
+                },
+                None => return Err(&input[index ..])
+            },
+            c if is_meta(c) => break,
+            c => end = index + c.len_utf8()
         }
     }
-
-    /// Push a given token to the buffer, negate if needed.
-    fn push(&mut self, p: Production<'a>) {
-        self.result.0.push(
-            if self.negate {
-                self.negate = false;
-                Production::Not(Box::new(p))
-            }
-            else {
-                p
-            }
-        );
+    if end == 0 {
+        return Err(input);
     }
+    let raw = &input[.. end];
+    let value = if has_escape { Cow::Owned(unescape(raw)) } else { Cow::Borrowed(raw) };
+    Ok((&input[end ..], Production::Sequence(value)))
+}
 
-    /// Clears active capture index.
-    fn reset_capture(&mut self) {
-        self.start = None;
+/// Matches `*`, producing an empty `ManyOf`.
+fn star(input: &str) -> ParseResult<'_, Production<'_>> {
+    literal_char('*').map(|_| Production::ManyOf(Vec::new())).parse(input)
+}
+
+/// Matches `?`, producing an empty `OneOf`.
+fn question(input: &str) -> ParseResult<'_, Production<'_>> {
+    literal_char('?').map(|_| Production::OneOf(Vec::new())).parse(input)
+}
+
+/// Parses a single character (other than `]`) into a `Choice::AnyOf`, or
+/// `\x` as a literal `x` (so `]` and `-` can be matched literally too).
+fn char_literal(input: &str) -> ParseResult<'_, Choice<'_>> {
+    if let Some(escaped) = input.strip_prefix('\\') {
+        let c = any_char.parse(escaped)?.1;
+        return Ok((&escaped[c.len_utf8() ..], Choice::AnyOf(&escaped[.. c.len_utf8()])));
     }
+    let (rest, c) = any_char.pred(|&c| c != ']').parse(input)?;
+    Ok((rest, Choice::AnyOf(&input[.. c.len_utf8()])))
+}
 
-    /// Starts active capture from `index`.
-    fn start_capture(&mut self, index: usize) {
-        self.start = Some(index);
+/// Parses a `from-to` range. Returns `None` if `input` doesn't start with
+/// that shape at all (so the caller can fall back to a plain character;
+/// in particular an escaped `\x` is never a range boundary), and
+/// `Some(Err(..))` with the offending chars and the position of their `-`
+/// if the range is present but back-to-front (`from > to`).
+fn char_range(input: &str) -> Option<ParseResult<'_, Choice<'_>>> {
+    if input.starts_with('\\') {
+        return None;
+    }
+    let (after_from, from) = any_char.parse(input).ok()?;
+    let dash = after_from;
+    let (after_dash, _) = literal_char('-').parse(after_from).ok()?;
+    if after_dash.starts_with('\\') {
+        return None;
     }
+    let (after_to, to) = any_char.parse(after_dash).ok()?;
+    Some(if to < from {
+        Err(dash)
+    } else {
+        Ok((after_to, Choice::Range(from, to)))
+    })
+}
 
-    /// Cuts a slice with the active capture if it's non-empty.
-    fn capture(&mut self, index: usize) -> Option<&'a str> {
-        if let Some(start) = self.start {
-            if index > start {
-                return Some(&self.source[start .. index])
-            }
+/// Parses the body of a `[...]` class up to and including its closing `]`:
+/// a leading `!` negation, then zero or more ranges or single characters,
+/// i.e. the choices of a `OneOf`. `open` is the `[` that started this
+/// class, kept around to report where an unterminated class opened.
+fn char_class<'a>(source: &'a str, open: &'a str, input: &'a str) -> Result<(&'a str, Production<'a>), WildcardParseError<'a>> {
+    let (mut cursor, negated) = match literal_char('!').parse(input) {
+        Ok((rest, _)) => (rest, true),
+        Err(_) => (input, false)
+    };
+    let mut choices = Vec::new();
+    loop {
+        if let Ok((rest, _)) = literal_char(']').parse(cursor) {
+            let production = Production::OneOf(choices);
+            return Ok((rest, if negated { Production::Not(Box::new(production)) } else { production }));
+        }
+        match char_range(cursor) {
+            Some(Ok((rest, choice))) => {
+                choices.push(choice);
+                cursor = rest;
+            },
+            Some(Err(dash)) => {
+                let (_, from) = any_char.parse(cursor).unwrap();
+                let (_, to) = any_char.parse(&dash[1 ..]).unwrap();
+                return Err(WildcardParseError::InvalidCharRange(from, to, position_of(source, dash)));
+            },
+            None =>
+                match char_literal(cursor) {
+                    Ok((rest, choice)) => {
+                        choices.push(choice);
+                        cursor = rest;
+                    },
+                    Err(_) =>
+                        return Err(WildcardParseError::Incomplete(position_of(source, open)))
+                }
         }
-        None
     }
+}
 
+/// Parses `[...]`, including its leading `!` negation, into a `OneOf` or
+/// `Not(OneOf)`.
+fn class<'a>(source: &'a str, input: &'a str) -> Option<Result<(&'a str, Production<'a>), WildcardParseError<'a>>> {
+    let (rest, _) = literal_char('[').parse(input).ok()?;
+    Some(char_class(source, input, rest))
 }
 
-impl std::fmt::Display for Production<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        use std::fmt::{Formatter, Result};
-        use Production::*;
-        fn run(p: &Production<'_>, f: &mut Formatter, negate: bool) -> Result {
-            match self {
-                Sequence(s) => write!(f, "{}", s),
-                ManyOf(_) => write!(f, "*"),
-                OneOf(choices) if choices.is_empty() => write!(f, "?"),
-                OneOf(choices) =>
-                    {
-                        write!(f, "[");
-                        if negate { write!(f, "!") };
-                        choices
-                            .iter()
-                            .try_for_each(|c| c.fmt(f))?;
-                        fmt.write_char(']')?;
-                        Ok(())
-                    },
-                Not(token) =>
-                    fmt_token(token, true, fmt)
-            }
-        }
-        match self {
-            Sequence(s) => s.fmt(f),
-            ManyOf(_) => '*'.fmt(f),
-            OneOf(choices) if choices.is_empty() => '?'.fmt(f),
-            OneOf(choices) =>
-                {
-                    fmt.write_char('[')?;
-                    if negate {
-                        fmt.write_char('!')?;
-                    }
-                    choices
-                        .iter()
-                        .try_for_each(|c| c.fmt(f))?;
-                    fmt.write_char(']')?;
-                    Ok(())
-                },
-            Not(token) =>
-                fmt_token(token, true, fmt)
-        }
+/// Parses a single production: a literal run, `*`, `?` or a `[...]` class.
+fn production<'a>(source: &'a str, input: &'a str) -> Result<(&'a str, Production<'a>), WildcardParseError<'a>> {
+    if let Ok(result) = star.or(question).parse(input) {
+        return Ok(result);
     }
+    if let Some(result) = class(source, input) {
+        return result;
+    }
+    sequence(input).map_err(|at| WildcardParseError::Incomplete(position_of(source, at)))
+}
+
+/// Parses `source` as a `Wildcard` grammar.
+fn parse_wildcard(source: &str) -> Result<Wildcard<'_>, WildcardParseError<'_>> {
+    let mut cursor = source;
+    let mut productions = Vec::new();
+    while !cursor.is_empty() {
+        let (rest, p) = production(source, cursor)?;
+        productions.push(p);
+        cursor = rest;
+    }
+    Ok(Wildcard(productions))
 }
 
 impl std::fmt::Display for Wildcard<'_> {
 
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         use std::fmt::{Formatter, Result};
-        fn fmt_choice(choice: &Choice<'_>, fmt: &mut Formatter) -> Result {
+        /// Writes `c`, escaping it with a leading `\` if it's special
+        /// within a `[...]` class (`]`, `-` or `\` itself), or if it's a
+        /// leading `!` that would otherwise be read back as negation.
+        fn fmt_choice_char(c: char, leading: bool, fmt: &mut Formatter) -> Result {
+            if matches!(c, ']' | '-' | '\\') || (leading && c == '!') {
+                fmt.write_char('\\')?;
+            }
+            fmt.write_char(c)
+        }
+        fn fmt_choice(choice: &Choice<'_>, leading: bool, fmt: &mut Formatter) -> Result {
             match choice {
                 Choice::AnyOf(s) =>
-                    fmt.write_str(s),
+                    fmt_choice_char(s.chars().next().expect("AnyOf choice always holds exactly one char"), leading, fmt),
                 Choice::Range(from, to) =>
-                    [*from, '-', *to]
-                        .iter()
-                        .try_for_each(|&c| fmt.write_char(c))
+                    {
+                        fmt_choice_char(*from, leading, fmt)?;
+                        fmt.write_char('-')?;
+                        fmt_choice_char(*to, false, fmt)
+                    }
             }
         }
-        fn fmt_token(token: &Token<'_>, negate: bool, fmt: &mut Formatter) -> Result {
-            match token {
-                Token::Sequence(s) =>
-                    fmt.write_str(s),
-                Token::ManyOf(_) =>
+        fn fmt_production(production: &Production<'_>, negate: bool, fmt: &mut Formatter) -> Result {
+            match production {
+                Production::Sequence(s) =>
+                    s.chars().try_for_each(|c| {
+                        if matches!(c, '*' | '?' | '[' | '\\') {
+                            fmt.write_char('\\')?;
+                        }
+                        fmt.write_char(c)
+                    }),
+                Production::ManyOf(_) =>
                     fmt.write_char('*'),
-                Token::OneOf(choices) if choices.is_empty() =>
+                Production::OneOf(choices) if choices.is_empty() && !negate =>
                     fmt.write_char('?'),
-                Token::OneOf(choices) =>
+                Production::OneOf(choices) =>
                     {
                         fmt.write_char('[')?;
                         if negate {
@@ -266,37 +298,345 @@ impl std::fmt::Display for Wildcard<'_> {
                         }
                         choices
                             .iter()
-                            .try_for_each(|c| fmt_choice(c, fmt))?;
+                            .enumerate()
+                            .try_for_each(|(i, c)| fmt_choice(c, i == 0 && !negate, fmt))?;
                         fmt.write_char(']')?;
                         Ok(())
                     },
-                Token::Not(token) =>
-                    fmt_token(token, true, fmt)
+                Production::Not(production) =>
+                    fmt_production(production, true, fmt)
             }
         }
         self.0
             .iter()
-            .try_for_each(|t| fmt_token(t, false, fmt))
+            .try_for_each(|p| fmt_production(p, false, fmt))
     }
 
 }
 
-// impl<'a> Wildcard<'a> {
+impl<'a> Wildcard<'a> {
 
-//     pub fn parse(source: &'a str) -> Result<Wildcard<'a>, WildcardParseError> {
-//         WildcardParser::new(source).run().map(|grammar| Self(grammar))
-//     }
+    /// Parses `source` as a wildcard pattern.
+    pub fn parse(source: &'a str) -> Result<Wildcard<'a>, WildcardParseError<'a>> {
+        parse_wildcard(source)
+    }
 
-//     /// Checks whether subject satisfies wildcard.
-//     pub fn matches(&self, subject: &str) -> bool {
-//         let iter = self.0.iter();
-//         self.matches_after(iter, subject)
-//     }
+    /// Checks whether subject satisfies wildcard.
+    pub fn matches(&self, subject: &str) -> bool {
+        let subject: Vec<char> = subject.chars().collect();
+        Self::matches_after(&self.0, &subject)
+    }
 
-//     fn matches_after<I>(&self, iter: I, subject: &str) -> bool where
-//         I: Iterator<Item = &'a Token<'a>>
-//     {
-//         false
-//     }
+    /// Matches `subject` (already split into `char`s) against `productions`
+    /// using the classic two-pointer backtracking glob algorithm: `p` walks
+    /// the productions, `s` walks the subject, and the last seen `ManyOf`
+    /// is remembered so a mismatch can retry it against one more char.
+    fn matches_after(productions: &[Production<'a>], subject: &[char]) -> bool {
+        let mut p = 0;
+        let mut s = 0;
+        let mut star_p: Option<usize> = None;
+        let mut star_s = 0;
 
-// }
+        loop {
+            if s == subject.len() {
+                return productions[p..]
+                    .iter()
+                    .all(|production| matches!(production, Production::ManyOf(_)));
+            }
+            if p < productions.len() {
+                if let Production::ManyOf(_) = &productions[p] {
+                    star_p = Some(p);
+                    star_s = s;
+                    p += 1;
+                    continue;
+                }
+                if Self::matches_one(&productions[p], subject, &mut s) {
+                    p += 1;
+                    continue;
+                }
+            }
+            match star_p {
+                Some(sp) => {
+                    star_s += 1;
+                    if star_s > subject.len() {
+                        return false;
+                    }
+                    p = sp + 1;
+                    s = star_s;
+                }
+                None => return false
+            }
+        }
+    }
+
+    /// Tests a single (non-`ManyOf`) production against `subject` starting
+    /// at `*s`, advancing `*s` past what it consumed on success.
+    fn matches_one(production: &Production<'a>, subject: &[char], s: &mut usize) -> bool {
+        match production {
+            Production::Sequence(literal) => {
+                let len = literal.chars().count();
+                if *s + len > subject.len() {
+                    return false;
+                }
+                if literal.chars().zip(&subject[*s .. *s + len]).all(|(a, b)| a == *b) {
+                    *s += len;
+                    true
+                } else {
+                    false
+                }
+            },
+            Production::OneOf(_) =>
+                if Self::matches_single_char(production, subject[*s]) {
+                    *s += 1;
+                    true
+                } else {
+                    false
+                },
+            Production::Not(inner) =>
+                if !Self::matches_single_char(inner, subject[*s]) {
+                    *s += 1;
+                    true
+                } else {
+                    false
+                },
+            Production::ManyOf(_) =>
+                unreachable!("ManyOf is handled by matches_after before matches_one is called")
+        }
+    }
+
+    /// Tests whether a single `char` satisfies a `OneOf` production.
+    fn matches_single_char(production: &Production<'a>, c: char) -> bool {
+        match production {
+            Production::OneOf(choices) if choices.is_empty() => true,
+            Production::OneOf(choices) => choices.iter().any(|choice| Self::matches_choice(choice, c)),
+            _ => false
+        }
+    }
+
+    fn matches_choice(choice: &Choice<'a>, c: char) -> bool {
+        match choice {
+            Choice::AnyOf(s) => s.contains(c),
+            Choice::Range(from, to) => *from <= c && c <= *to
+        }
+    }
+
+}
+
+/// Accumulates wildcard source text fed to it piecemeal — e.g. as a user
+/// types a pattern, or as bytes arrive over a stream — so that "not
+/// finished yet" (an open `[...]` class, a trailing `\`) can be told apart
+/// from "already broken" (an out-of-order char range) before the whole
+/// pattern has arrived.
+///
+/// `Production` and `Choice` borrow directly from the text they were
+/// parsed from, and that text here is the buffer owned by this builder;
+/// there's nowhere to stash an in-progress `Vec<Production<'_>>` between
+/// `feed` calls without tying it unsafely to a buffer that's still
+/// growing. So `finish` re-parses the whole buffer every time it's
+/// called — resumable in the sense that the caller doesn't have to
+/// re-send earlier chunks, but not in the sense of resuming a
+/// partially-built production list.
+#[derive(Debug, Default)]
+pub struct WildcardBuilder {
+    buffer: String
+}
+
+impl WildcardBuilder {
+    /// Creates a builder with nothing fed to it yet.
+    pub fn new() -> Self { Self { buffer: String::new() } }
+
+    /// Appends `more` to the source text fed so far.
+    pub fn feed(&mut self, more: &str) {
+        self.buffer.push_str(more);
+    }
+
+    /// Re-parses everything fed so far as a complete `Wildcard`.
+    ///
+    /// An `Err(Incomplete(_))` means the buffer isn't a full pattern
+    /// yet but could still become one — e.g. it ends inside an open
+    /// `[...]` class, or with a trailing `\` — so the caller should
+    /// `feed` more and call `finish` again. Any other error is final:
+    /// no amount of further input fixes an out-of-order char range.
+    pub fn finish(&self) -> Result<Wildcard<'_>, WildcardParseError<'_>> {
+        parse_wildcard(&self.buffer)
+    }
+
+    /// Whether `finish`'s current error, if any, could still be resolved
+    /// by feeding more input rather than being a definite syntax error.
+    pub fn needs_more(&self) -> bool {
+        matches!(self.finish(), Err(WildcardParseError::Incomplete(_)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Wildcard<'_> {
+        Wildcard::parse(source).unwrap()
+    }
+
+    #[test]
+    fn matches_plain_sequence() {
+        assert!(parse("abc").matches("abc"));
+        assert!(!parse("abc").matches("abd"));
+        assert!(!parse("abc").matches("abcd"));
+    }
+
+    #[test]
+    fn matches_empty_pattern() {
+        assert!(parse("").matches(""));
+        assert!(!parse("").matches("a"));
+    }
+
+    #[test]
+    fn matches_question_mark() {
+        assert!(parse("a?c").matches("abc"));
+        assert!(!parse("a?c").matches("ac"));
+    }
+
+    #[test]
+    fn matches_star() {
+        assert!(parse("*").matches(""));
+        assert!(parse("*").matches("anything"));
+        assert!(parse("*a*b*c").matches("xxaxxbxxc"));
+        assert!(parse("*a*b*c").matches("abc"));
+        assert!(!parse("*a*b*c").matches("cab"));
+    }
+
+    #[test]
+    fn matches_trailing_star() {
+        assert!(parse("abc*").matches("abc"));
+        assert!(parse("abc*").matches("abcdef"));
+        assert!(!parse("abc*").matches("ab"));
+    }
+
+    #[test]
+    fn matches_char_class() {
+        assert!(parse("[a-z]").matches("m"));
+        assert!(!parse("[a-z]").matches("M"));
+        assert!(parse("[abc]").matches("b"));
+        assert!(!parse("[abc]").matches("d"));
+    }
+
+    #[test]
+    fn matches_negated_char_class() {
+        assert!(parse("[!a-z]").matches("M"));
+        assert!(!parse("[!a-z]").matches("m"));
+        assert!(parse("[!abc]").matches("d"));
+        assert!(!parse("[!abc]").matches("a"));
+    }
+
+    #[test]
+    fn matches_multibyte_subject() {
+        assert!(parse("???").matches("日本語"));
+        assert!(parse("[あ-ん]*").matches("ひらがな"));
+        assert!(!parse("[あ-ん]*").matches("カタカナ"));
+    }
+
+    #[test]
+    fn reports_position_of_unterminated_class() {
+        let error = Wildcard::parse("abc[def").unwrap_err();
+        assert_eq!(error, WildcardParseError::Incomplete(Position { source: "abc[def", index: 3 }));
+    }
+
+    #[test]
+    fn reports_position_of_invalid_char_range() {
+        let error = Wildcard::parse("[z-a]").unwrap_err();
+        assert_eq!(error, WildcardParseError::InvalidCharRange('z', 'a', Position { source: "[z-a]", index: 2 }));
+    }
+
+    #[test]
+    fn display_points_a_caret_at_the_error() {
+        let error = Wildcard::parse("[z-a]").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid char range 'z-a': 'z' is past 'a':\n[z-a]\n  ^"
+        );
+    }
+
+    #[test]
+    fn matches_escaped_metachar_at_start() {
+        assert!(parse("\\*a").matches("*a"));
+        assert!(!parse("\\*a").matches("xa"));
+    }
+
+    #[test]
+    fn matches_escaped_metachar_in_middle() {
+        assert!(parse("a\\*b").matches("a*b"));
+        assert!(!parse("a\\*b").matches("axb"));
+    }
+
+    #[test]
+    fn matches_escaped_metachar_at_end() {
+        assert!(parse("ab\\?").matches("ab?"));
+        assert!(!parse("ab\\?").matches("abc"));
+    }
+
+    #[test]
+    fn matches_escaped_chars_inside_class() {
+        assert!(parse("[a\\]b]").matches("]"));
+        assert!(parse("[a\\]b]").matches("a"));
+        assert!(parse("[a\\-b]").matches("-"));
+        assert!(!parse("[a\\-b]").matches("c"));
+    }
+
+    #[test]
+    fn trailing_backslash_is_incomplete() {
+        let error = Wildcard::parse("ab\\").unwrap_err();
+        assert_eq!(error, WildcardParseError::Incomplete(Position { source: "ab\\", index: 2 }));
+    }
+
+    #[test]
+    fn escaped_metachar_round_trips_through_display() {
+        let wildcard = parse("a\\*b\\?c");
+        assert_eq!(wildcard.to_string(), "a\\*b\\?c");
+    }
+
+    #[test]
+    fn leading_escaped_bang_round_trips_through_display() {
+        let wildcard = parse("[\\!a]");
+        assert_eq!(wildcard.to_string(), "[\\!a]");
+        assert!(wildcard.matches("a"));
+        assert!(wildcard.matches("!"));
+        assert!(!wildcard.matches("b"));
+    }
+
+    #[test]
+    fn empty_negated_class_round_trips_through_display() {
+        let wildcard = parse("[!]");
+        assert_eq!(wildcard.to_string(), "[!]");
+        assert!(!wildcard.matches("a"));
+    }
+
+    #[test]
+    fn builder_resumes_across_an_open_class() {
+        let mut builder = WildcardBuilder::new();
+        builder.feed("abc[de");
+        assert!(builder.needs_more());
+        builder.feed("f]*");
+        assert!(!builder.needs_more());
+        assert!(builder.finish().unwrap().matches("abcdxyz"));
+    }
+
+    #[test]
+    fn builder_resumes_across_a_trailing_backslash() {
+        let mut builder = WildcardBuilder::new();
+        builder.feed("ab\\");
+        assert!(builder.needs_more());
+        builder.feed("*c");
+        assert!(!builder.needs_more());
+        assert!(builder.finish().unwrap().matches("ab*c"));
+    }
+
+    #[test]
+    fn builder_reports_a_definite_error_without_waiting_for_more() {
+        let mut builder = WildcardBuilder::new();
+        builder.feed("[z-a]");
+        assert!(!builder.needs_more());
+        assert!(matches!(
+            builder.finish().unwrap_err(),
+            WildcardParseError::InvalidCharRange('z', 'a', _)
+        ));
+    }
+}